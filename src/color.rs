@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+
+/// Converts a `#rrggbb` hex color to (hue 0-360, saturation 0-100, value 0-100).
+pub fn hex_to_hsv(hex: &str) -> Result<(u16, u8, u8)> {
+    let hex = hex.trim_start_matches('#');
+    if hex.len() != 6 {
+        anyhow::bail!("Invalid hex color: {}", hex);
+    }
+
+    let r = u8::from_str_radix(&hex[0..2], 16).context("Invalid red channel")? as f64 / 255.0;
+    let g = u8::from_str_radix(&hex[2..4], 16).context("Invalid green channel")? as f64 / 255.0;
+    let b = u8::from_str_radix(&hex[4..6], 16).context("Invalid blue channel")? as f64 / 255.0;
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * (((g - b) / delta) % 6.0)
+    } else if max == g {
+        60.0 * (((b - r) / delta) + 2.0)
+    } else {
+        60.0 * (((r - g) / delta) + 4.0)
+    };
+    let hue = if hue < 0.0 { hue + 360.0 } else { hue };
+
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+    Ok((
+        hue.round() as u16,
+        (saturation * 100.0).round() as u8,
+        (max * 100.0).round() as u8,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pure_red_is_hue_zero_full_saturation_and_value() {
+        assert_eq!(hex_to_hsv("#ff0000").unwrap(), (0, 100, 100));
+    }
+
+    #[test]
+    fn white_has_zero_saturation() {
+        assert_eq!(hex_to_hsv("#ffffff").unwrap(), (0, 0, 100));
+    }
+
+    #[test]
+    fn black_has_zero_value() {
+        assert_eq!(hex_to_hsv("#000000").unwrap(), (0, 0, 0));
+    }
+
+    #[test]
+    fn dim_red_keeps_saturation_but_lowers_value() {
+        let (h, s, v) = hex_to_hsv("#330000").unwrap();
+        assert_eq!(h, 0);
+        assert_eq!(s, 100);
+        assert!(v < 25, "expected a low value channel, got {}", v);
+    }
+
+    #[test]
+    fn accepts_hex_without_leading_hash() {
+        assert_eq!(hex_to_hsv("ff8800").unwrap(), hex_to_hsv("#ff8800").unwrap());
+    }
+
+    #[test]
+    fn rejects_wrong_length() {
+        assert!(hex_to_hsv("#fff").is_err());
+    }
+}
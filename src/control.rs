@@ -0,0 +1,58 @@
+use anyhow::Result;
+use rayon::prelude::*;
+
+/// Anything that can push a single characteristic value to a named device.
+///
+/// Implemented by each binary's `SmartHomeAgent` so [`control_many`] doesn't need to know
+/// how a device lookup or the underlying HTTP PUT actually works.
+pub trait DeviceController {
+    fn control_device(&self, device: &str, characteristic: &str, value: serde_json::Value) -> Result<()>;
+
+    /// The service names of all known devices, used to expand group glob patterns.
+    fn device_names(&self) -> Vec<String>;
+}
+
+/// Dispatches a batch of `(device, characteristic, value)` operations concurrently,
+/// so one unreachable device can't stall the rest. Falls back to running them
+/// sequentially if a dedicated thread pool can't be built.
+pub fn control_many<C: DeviceController + Sync>(
+    controller: &C,
+    ops: Vec<(String, String, serde_json::Value)>,
+) -> Vec<(String, Result<()>)> {
+    let run_op = |device: String, characteristic: String, value: serde_json::Value| {
+        let result = controller.control_device(&device, &characteristic, value);
+        (device, result)
+    };
+
+    let results: Vec<(String, Result<()>)> = match rayon::ThreadPoolBuilder::new()
+        .num_threads(ops.len().clamp(1, 8))
+        .build()
+    {
+        Ok(pool) => pool.install(|| {
+            ops.into_par_iter()
+                .map(|(device, characteristic, value)| run_op(device, characteristic, value))
+                .collect()
+        }),
+        Err(e) => {
+            eprintln!("⚠️  Failed to build thread pool, falling back to sequential execution: {}", e);
+            ops.into_iter()
+                .map(|(device, characteristic, value)| run_op(device, characteristic, value))
+                .collect()
+        }
+    };
+
+    let failures: Vec<&(String, Result<()>)> = results.iter().filter(|(_, r)| r.is_err()).collect();
+
+    println!(
+        "\n📊 {} succeeded, {} failed",
+        results.len() - failures.len(),
+        failures.len()
+    );
+    for (device, result) in failures {
+        if let Err(e) = result {
+            println!("  ❌ {}: {}", device, e);
+        }
+    }
+
+    results
+}
@@ -0,0 +1,73 @@
+/// Matches `text` against a `pattern` that may contain `*` wildcards, case-insensitively.
+///
+/// Uses the standard backtracking two-pointer algorithm rather than a greedy single pass,
+/// so repeated substrings in `text` (e.g. `glob_match("a*bb", "abbb")`) match correctly.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, t));
+        } else {
+            return false;
+        }
+    }
+
+    while p < pattern.len() && pattern[p] == '*' {
+        p += 1;
+    }
+
+    p == pattern.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_exact_text() {
+        assert!(glob_match("kitchen", "kitchen"));
+    }
+
+    #[test]
+    fn matches_prefix_wildcard() {
+        assert!(glob_match("kitchen*", "kitchen lamp"));
+    }
+
+    #[test]
+    fn matches_suffix_wildcard() {
+        assert!(glob_match("*lamp", "bedroom lamp"));
+    }
+
+    #[test]
+    fn matches_repeated_substring_requiring_backtrack() {
+        assert!(glob_match("a*bb", "abbb"));
+    }
+
+    #[test]
+    fn rejects_non_matching_text() {
+        assert!(!glob_match("a*bb", "abcb"));
+    }
+
+    #[test]
+    fn is_case_insensitive_on_pattern() {
+        assert!(glob_match("KITCHEN*", "kitchen lamp"));
+    }
+
+    #[test]
+    fn is_case_insensitive_on_text() {
+        assert!(glob_match("kitchen*", "KITCHEN LAMP"));
+    }
+}
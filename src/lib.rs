@@ -0,0 +1,7 @@
+//! Shared building blocks for the `smart-home-agent` and `smart-home-llm` binaries.
+
+pub mod color;
+pub mod control;
+pub mod glob;
+pub mod scenes;
+pub mod token_cache;
@@ -1,9 +1,12 @@
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use reqwest::blocking::Client;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use smart_home_agent::control::{control_many, DeviceController};
+use smart_home_agent::scenes::{load_config, resolve_step_devices, step_ops, Config};
+use smart_home_agent::token_cache::{load_cached_token, save_cached_token};
 use std::env;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "smart-home-llm")]
@@ -23,6 +26,34 @@ struct Cli {
 
     #[arg(long, env = "ANTHROPIC_API_KEY")]
     anthropic_api_key: Option<String>,
+
+    #[arg(long, env = "OPENAI_API_KEY")]
+    openai_api_key: Option<String>,
+
+    /// Base URL for an OpenAI-compatible server
+    #[arg(long, env = "OPENAI_BASE_URL", default_value = "https://api.openai.com")]
+    openai_base_url: String,
+
+    #[arg(long, env = "OPENAI_MODEL", default_value = "gpt-4o-mini")]
+    openai_model: String,
+
+    /// Which LLM backend to use; defaults to whichever API key is set
+    #[arg(long, value_enum)]
+    provider: Option<Provider>,
+
+    /// HTTP request timeout in seconds
+    #[arg(long, default_value_t = 5)]
+    timeout: u64,
+
+    /// Path to a scenes/groups config file (default: ~/.config/smart-home-agent/scenes.toml)
+    #[arg(long)]
+    config: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Provider {
+    Anthropic,
+    Openai,
 }
 
 #[derive(Debug, Deserialize)]
@@ -38,7 +69,6 @@ struct Accessory {
     service_name: String,
     #[serde(rename = "type")]
     device_type: String,
-    values: Option<HashMap<String, serde_json::Value>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -72,12 +102,48 @@ struct ClaudeContent {
     text: String,
 }
 
+#[derive(Debug, Serialize)]
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<ChatCompletionChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChoice {
+    message: ChatCompletionMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionMessage {
+    content: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct Action {
-    device: String,
+    #[serde(default)]
+    device: Option<String>,
     action: String,
     #[serde(default)]
     brightness: Option<u8>,
+    #[serde(default)]
+    hue: Option<u16>,
+    #[serde(default)]
+    saturation: Option<u8>,
+    #[serde(default)]
+    color_temp: Option<u16>,
+    #[serde(default)]
+    scene: Option<String>,
 }
 
 struct SmartHomeAgent {
@@ -88,33 +154,43 @@ struct SmartHomeAgent {
 }
 
 impl SmartHomeAgent {
-    fn new(homebridge_url: String, username: String, password: String) -> Result<Self> {
-        let client = Client::new();
-
-        // Authenticate
-        println!("🔐 Authenticating with Homebridge...");
-        let login_response: LoginResponse = client
-            .post(format!("{}/api/auth/login", homebridge_url))
-            .json(&serde_json::json!({
-                "username": username,
-                "password": password
-            }))
-            .send()
-            .context("Failed to authenticate")?
-            .json()
-            .context("Failed to parse login response")?;
-
-        let token = login_response.access_token;
+    fn new(
+        homebridge_url: String,
+        username: String,
+        password: String,
+        timeout_secs: u64,
+    ) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(timeout_secs))
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        let mut token = if let Some(cached) = load_cached_token(&homebridge_url, &username) {
+            println!("🔐 Using cached access token");
+            cached
+        } else {
+            Self::login(&client, &homebridge_url, &username, &password)?
+        };
 
-        // Discover devices
+        // Discover devices, re-authenticating once if the cached token was rejected.
         println!("🔍 Discovering devices...");
-        let devices: Vec<Accessory> = client
+        let mut response = client
             .get(format!("{}/api/accessories", homebridge_url))
             .bearer_auth(&token)
             .send()
-            .context("Failed to fetch accessories")?
-            .json()
-            .context("Failed to parse accessories")?;
+            .context("Failed to fetch accessories")?;
+
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            println!("🔐 Cached token was rejected, re-authenticating...");
+            token = Self::login(&client, &homebridge_url, &username, &password)?;
+            response = client
+                .get(format!("{}/api/accessories", homebridge_url))
+                .bearer_auth(&token)
+                .send()
+                .context("Failed to fetch accessories")?;
+        }
+
+        let devices: Vec<Accessory> = response.json().context("Failed to parse accessories")?;
 
         println!("✅ Found {} devices\n", devices.len());
 
@@ -126,7 +202,28 @@ impl SmartHomeAgent {
         })
     }
 
-    fn get_device_list(&self) -> String {
+    /// Logs in via `/api/auth/login` and persists the resulting token to the on-disk cache.
+    fn login(client: &Client, homebridge_url: &str, username: &str, password: &str) -> Result<String> {
+        println!("🔐 Authenticating with Homebridge...");
+        let login_response: LoginResponse = client
+            .post(format!("{}/api/auth/login", homebridge_url))
+            .json(&serde_json::json!({
+                "username": username,
+                "password": password
+            }))
+            .send()
+            .context("Failed to authenticate")?
+            .json()
+            .context("Failed to parse login response")?;
+
+        let token = login_response.access_token;
+        if let Err(e) = save_cached_token(homebridge_url, username, &token) {
+            eprintln!("⚠️  Failed to cache access token: {}", e);
+        }
+        Ok(token)
+    }
+
+    fn get_device_list(&self, config: &Config) -> String {
         let controllable: Vec<String> = self
             .devices
             .iter()
@@ -134,7 +231,41 @@ impl SmartHomeAgent {
             .map(|d| d.service_name.clone())
             .collect();
 
-        controllable.join(", ")
+        let mut description = format!("Devices: {}", controllable.join(", "));
+
+        if !config.groups.is_empty() {
+            let names: Vec<&str> = config.groups.keys().map(|s| s.as_str()).collect();
+            description.push_str(&format!("\nGroups: {}", names.join(", ")));
+        }
+
+        if !config.scenes.is_empty() {
+            let names: Vec<&str> = config.scenes.keys().map(|s| s.as_str()).collect();
+            description.push_str(&format!("\nScenes: {}", names.join(", ")));
+        }
+
+        description
+    }
+
+    fn run_scene(&self, config: &Config, scene_name: &str) -> Result<()> {
+        let scene = config
+            .scenes
+            .get(scene_name)
+            .with_context(|| format!("Unknown scene: {}", scene_name))?;
+
+        let known_devices = self.device_names();
+        for step in &scene.steps {
+            let devices = resolve_step_devices(config, step, &known_devices)?;
+            let mut ops = Vec::new();
+            for device in devices {
+                ops.extend(step_ops(device, step)?);
+            }
+            let results = control_many(self, ops);
+            if results.iter().any(|(_, r)| r.is_err()) {
+                anyhow::bail!("One or more devices in scene '{}' failed", scene_name);
+            }
+        }
+
+        Ok(())
     }
 
     fn find_device(&self, query: &str) -> Option<&Accessory> {
@@ -192,39 +323,77 @@ impl SmartHomeAgent {
 
         Ok(())
     }
+}
+
+impl DeviceController for SmartHomeAgent {
+    fn control_device(&self, device: &str, characteristic: &str, value: serde_json::Value) -> Result<()> {
+        SmartHomeAgent::control_device(self, device, characteristic, value)
+    }
 
-    fn execute_action(&self, action: &Action) -> Result<()> {
-        match action.action.as_str() {
-            "on" => self.control_device(&action.device, "On", serde_json::json!(1)),
-            "off" => self.control_device(&action.device, "On", serde_json::json!(0)),
-            "brightness" => {
-                if let Some(level) = action.brightness {
-                    self.control_device(&action.device, "Brightness", serde_json::json!(level))
-                } else {
-                    anyhow::bail!("Brightness action requires brightness value")
-                }
-            }
-            _ => anyhow::bail!("Unknown action: {}", action.action),
-        }
+    fn device_names(&self) -> Vec<String> {
+        self.devices.iter().map(|d| d.service_name.clone()).collect()
     }
 }
 
-fn parse_order_with_claude(
-    api_key: &str,
-    order: &str,
-    device_list: &str,
-) -> Result<Vec<Action>> {
-    let client = Client::new();
+/// Resolves an `Action` to the `(device, characteristic, value)` triple `control_device` expects.
+fn action_to_op(action: &Action) -> Result<(String, String, serde_json::Value)> {
+    let (characteristic, value) = match action.action.as_str() {
+        "on" => ("On", serde_json::json!(1)),
+        "off" => ("On", serde_json::json!(0)),
+        "brightness" => {
+            let level = action
+                .brightness
+                .context("Brightness action requires brightness value")?;
+            ("Brightness", serde_json::json!(level))
+        }
+        "hue" => {
+            let hue = action.hue.context("Hue action requires hue value")?;
+            ("Hue", serde_json::json!(hue.min(360)))
+        }
+        "saturation" => {
+            let saturation = action
+                .saturation
+                .context("Saturation action requires saturation value")?;
+            ("Saturation", serde_json::json!(saturation.min(100)))
+        }
+        "color_temp" => {
+            let temp = action
+                .color_temp
+                .context("color_temp action requires color_temp value")?;
+            ("ColorTemperature", serde_json::json!(temp.clamp(140, 500)))
+        }
+        _ => anyhow::bail!("Unknown action: {}", action.action),
+    };
+
+    let device = action
+        .device
+        .clone()
+        .with_context(|| format!("{} action requires a device", action.action))?;
+
+    Ok((device, characteristic.to_string(), value))
+}
+
+/// Parses a natural-language order into a list of `Action`s using some hosted or local LLM.
+trait LlmProvider {
+    fn parse_order(&self, order: &str, device_list: &str) -> Result<Vec<Action>>;
+}
 
-    let system_prompt = format!(
+fn build_system_prompt(device_list: &str) -> String {
+    format!(
         r#"You are a smart home automation assistant. Your job is to parse natural language commands and convert them to JSON actions.
 
-Available devices: {}
+{}
 
 Return ONLY a JSON array of actions, with NO additional text. Each action must have:
-- "device": exact device name from the list above (use partial matching if needed)
-- "action": one of "on", "off", or "brightness"
+- "device": exact device name from the list above (use partial matching if needed); omit for a scene action
+- "action": one of "on", "off", "brightness", "hue", "saturation", "color_temp", or "scene"
 - "brightness": optional number 0-100 (only for brightness action)
+- "hue": optional number 0-360 (only for hue action)
+- "saturation": optional number 0-100 (only for saturation action)
+- "color_temp": optional number 140-500 mireds, lower is cooler (only for color_temp action)
+- "scene": exact scene name from the list above (only for scene action)
+
+Prefer a scene action whenever the order matches a known scene name instead of guessing individual devices.
 
 Examples:
 Input: "turn on kitchen lights"
@@ -236,55 +405,155 @@ Output: [{{"device": "Wohnzimmer Deckenlampe", "action": "brightness", "brightne
 Input: "lights off in office"
 Output: [{{"device": "Arbeitszimmer Deckenlampe", "action": "off"}}]
 
+Input: "make the bedroom lamp red"
+Output: [{{"device": "Schlafzimmer Lampe", "action": "hue", "hue": 0}}, {{"device": "Schlafzimmer Lampe", "action": "saturation", "saturation": 100}}]
+
+Input: "set office to warm white"
+Output: [{{"device": "Arbeitszimmer Deckenlampe", "action": "color_temp", "color_temp": 370}}]
+
+Input: "activate movie night"
+Output: [{{"action": "scene", "scene": "movie night"}}]
+
 Return ONLY valid JSON, nothing else."#,
         device_list
-    );
-
-    let request = ClaudeRequest {
-        model: "claude-3-5-haiku-20241022".to_string(),
-        max_tokens: 1024,
-        messages: vec![ClaudeMessage {
-            role: "user".to_string(),
-            content: order.to_string(),
-        }],
-        system: system_prompt,
-    };
+    )
+}
+
+fn parse_actions_from_text(text: &str) -> Result<Vec<Action>> {
+    serde_json::from_str(text).context("Failed to parse actions from LLM response")
+}
+
+struct AnthropicProvider {
+    api_key: String,
+}
+
+impl LlmProvider for AnthropicProvider {
+    fn parse_order(&self, order: &str, device_list: &str) -> Result<Vec<Action>> {
+        let client = Client::new();
+
+        let request = ClaudeRequest {
+            model: "claude-3-5-haiku-20241022".to_string(),
+            max_tokens: 1024,
+            messages: vec![ClaudeMessage {
+                role: "user".to_string(),
+                content: order.to_string(),
+            }],
+            system: build_system_prompt(device_list),
+        };
+
+        println!("🤖 Asking Claude Haiku to parse: \"{}\"", order);
+
+        let response: ClaudeResponse = client
+            .post("https://api.anthropic.com/v1/messages")
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .json(&request)
+            .send()
+            .context("Failed to call Claude API")?
+            .json()
+            .context("Failed to parse Claude response")?;
+
+        let text = response
+            .content
+            .first()
+            .context("No content in Claude response")?
+            .text
+            .as_str();
+
+        println!("📝 Claude response: {}\n", text);
+
+        parse_actions_from_text(text)
+    }
+}
+
+struct OpenAiProvider {
+    api_key: String,
+    base_url: String,
+    model: String,
+}
+
+impl LlmProvider for OpenAiProvider {
+    fn parse_order(&self, order: &str, device_list: &str) -> Result<Vec<Action>> {
+        let client = Client::new();
+
+        let request = ChatCompletionRequest {
+            model: self.model.clone(),
+            messages: vec![
+                ChatMessage {
+                    role: "system".to_string(),
+                    content: build_system_prompt(device_list),
+                },
+                ChatMessage {
+                    role: "user".to_string(),
+                    content: order.to_string(),
+                },
+            ],
+        };
 
-    println!("🤖 Asking Claude Haiku to parse: \"{}\"", order);
+        println!("🤖 Asking {} to parse: \"{}\"", self.model, order);
 
-    let response: ClaudeResponse = client
-        .post("https://api.anthropic.com/v1/messages")
-        .header("x-api-key", api_key)
-        .header("anthropic-version", "2023-06-01")
-        .json(&request)
-        .send()
-        .context("Failed to call Claude API")?
-        .json()
-        .context("Failed to parse Claude response")?;
+        let response: ChatCompletionResponse = client
+            .post(format!("{}/v1/chat/completions", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&request)
+            .send()
+            .context("Failed to call OpenAI-compatible API")?
+            .json()
+            .context("Failed to parse OpenAI-compatible response")?;
 
-    let text = response
-        .content
-        .first()
-        .context("No content in Claude response")?
-        .text
-        .as_str();
+        let text = response
+            .choices
+            .into_iter()
+            .next()
+            .context("No choices in OpenAI-compatible response")?
+            .message
+            .content;
 
-    println!("📝 Claude response: {}\n", text);
+        println!("📝 {} response: {}\n", self.model, text);
 
-    let actions: Vec<Action> =
-        serde_json::from_str(text).context("Failed to parse actions from Claude response")?;
+        parse_actions_from_text(&text)
+    }
+}
 
-    Ok(actions)
+fn resolve_provider(cli: &Cli) -> Result<Box<dyn LlmProvider>> {
+    let anthropic_key = cli
+        .anthropic_api_key
+        .clone()
+        .or_else(|| env::var("ANTHROPIC_API_KEY").ok());
+    let openai_key = cli
+        .openai_api_key
+        .clone()
+        .or_else(|| env::var("OPENAI_API_KEY").ok());
+
+    let provider = match cli.provider {
+        Some(Provider::Anthropic) => Provider::Anthropic,
+        Some(Provider::Openai) => Provider::Openai,
+        None if anthropic_key.is_some() => Provider::Anthropic,
+        None if openai_key.is_some() => Provider::Openai,
+        None => anyhow::bail!(
+            "No LLM provider configured: set ANTHROPIC_API_KEY, OPENAI_API_KEY, or pass --provider"
+        ),
+    };
+
+    match provider {
+        Provider::Anthropic => Ok(Box::new(AnthropicProvider {
+            api_key: anthropic_key
+                .context("ANTHROPIC_API_KEY is required (via --anthropic-api-key or env var)")?,
+        })),
+        Provider::Openai => Ok(Box::new(OpenAiProvider {
+            api_key: openai_key
+                .context("OPENAI_API_KEY is required (via --openai-api-key or env var)")?,
+            base_url: cli.openai_base_url.clone(),
+            model: cli.openai_model.clone(),
+        })),
+    }
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    // Get Anthropic API key
-    let api_key = cli
-        .anthropic_api_key
-        .or_else(|| env::var("ANTHROPIC_API_KEY").ok())
-        .context("ANTHROPIC_API_KEY is required (via --anthropic-api-key or env var)")?;
+    // Resolve which LLM backend to use
+    let provider = resolve_provider(&cli)?;
 
     // Get Homebridge credentials
     let username = cli
@@ -301,13 +570,16 @@ fn main() -> Result<()> {
     println!("📋 Order: {}\n", cli.order);
 
     // Initialize agent
-    let agent = SmartHomeAgent::new(cli.homebridge_url, username, password)?;
+    let agent = SmartHomeAgent::new(cli.homebridge_url, username, password, cli.timeout)?;
+
+    // Load scenes/groups config
+    let config = load_config(cli.config.as_deref())?;
 
-    // Get device list
-    let device_list = agent.get_device_list();
+    // Get device list, including known groups and scenes
+    let device_list = agent.get_device_list(&config);
 
-    // Parse order using Claude
-    let actions = parse_order_with_claude(&api_key, &cli.order, &device_list)?;
+    // Parse order using the resolved LLM backend
+    let actions = provider.parse_order(&cli.order, &device_list)?;
 
     if actions.is_empty() {
         println!("⚠️  No actions to execute");
@@ -316,10 +588,29 @@ fn main() -> Result<()> {
 
     println!("🎯 Executing {} action(s)...\n", actions.len());
 
-    // Execute actions
-    for (i, action) in actions.iter().enumerate() {
-        println!("[{}/{}] {:?}", i + 1, actions.len(), action);
-        agent.execute_action(action)?;
+    for action in &actions {
+        println!("  • {:?}", action);
+    }
+
+    // Run any scene actions directly, and batch the rest into device ops
+    let mut device_ops = Vec::new();
+    for action in &actions {
+        if action.action == "scene" {
+            let scene_name = action
+                .scene
+                .as_ref()
+                .context("scene action requires a scene name")?;
+            agent.run_scene(&config, scene_name)?;
+        } else {
+            device_ops.push(action_to_op(action)?);
+        }
+    }
+
+    if !device_ops.is_empty() {
+        let results = control_many(&agent, device_ops);
+        if results.iter().any(|(_, r)| r.is_err()) {
+            anyhow::bail!("One or more actions failed");
+        }
     }
 
     println!("\n🎉 All actions completed successfully!");
@@ -1,9 +1,16 @@
 use anyhow::{Context, Result};
+use chrono::Utc;
 use clap::{Parser, Subcommand};
 use reqwest::blocking::Client;
 use serde::{Deserialize, Serialize};
+use smart_home_agent::color::hex_to_hsv;
+use smart_home_agent::control::{control_many, DeviceController};
+use smart_home_agent::glob::glob_match;
+use smart_home_agent::scenes::{load_config, resolve_group_devices, resolve_step_devices, step_ops, Config};
+use smart_home_agent::token_cache::{load_cached_token, save_cached_token};
 use std::collections::HashMap;
 use std::process::Command;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "smart-home-agent")]
@@ -18,6 +25,14 @@ struct Cli {
     #[arg(long)]
     password: Option<String>,
 
+    /// HTTP request timeout in seconds
+    #[arg(long, default_value_t = 5)]
+    timeout: u64,
+
+    /// Path to a scenes/groups config file (default: ~/.config/smart-home-agent/scenes.toml)
+    #[arg(long)]
+    config: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -52,6 +67,53 @@ enum Commands {
         /// State: on, off, ein, aus
         state: String,
     },
+
+    /// Set device color via hex RGB, HSV, or color temperature
+    Color {
+        /// Device name (partial match supported)
+        device: String,
+
+        /// Hex RGB color, e.g. #ff8800
+        #[arg(long)]
+        hex: Option<String>,
+
+        /// Hue in degrees (0-360)
+        #[arg(long)]
+        hue: Option<u16>,
+
+        /// Saturation percentage (0-100)
+        #[arg(long)]
+        saturation: Option<u8>,
+
+        /// Color temperature in mireds (140-500, lower = cooler)
+        #[arg(long)]
+        temp: Option<u16>,
+    },
+
+    /// Poll devices and print a diff whenever a watched characteristic changes
+    Watch {
+        /// Poll interval in seconds
+        #[arg(long, default_value_t = 5)]
+        interval: u64,
+
+        /// Only watch devices whose name matches this pattern (supports * wildcards)
+        #[arg(long)]
+        filter: Option<String>,
+    },
+
+    /// Turn a named group of devices on or off
+    Group {
+        /// Group name, as defined in the scenes config
+        name: String,
+        /// State: on, off, ein, aus
+        state: String,
+    },
+
+    /// Run a named scene defined in the scenes config
+    Scene {
+        /// Scene name, as defined in the scenes config
+        name: String,
+    },
 }
 
 #[derive(Debug, Deserialize)]
@@ -92,10 +154,49 @@ struct SmartHomeAgent {
 }
 
 impl SmartHomeAgent {
-    fn new(base_url: String, username: String, password: String) -> Result<Self> {
-        let client = Client::new();
+    fn new(base_url: String, username: String, password: String, timeout_secs: u64) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(timeout_secs))
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        let mut token = if let Some(cached) = load_cached_token(&base_url, &username) {
+            println!("🔐 Using cached access token");
+            cached
+        } else {
+            Self::login(&client, &base_url, &username, &password)?
+        };
+
+        // Discover devices, re-authenticating once if the cached token was rejected.
+        println!("🔍 Discovering devices...\n");
+        let mut response = client
+            .get(format!("{}/api/accessories", base_url))
+            .bearer_auth(&token)
+            .send()
+            .context("Failed to fetch accessories")?;
+
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            println!("🔐 Cached token was rejected, re-authenticating...");
+            token = Self::login(&client, &base_url, &username, &password)?;
+            response = client
+                .get(format!("{}/api/accessories", base_url))
+                .bearer_auth(&token)
+                .send()
+                .context("Failed to fetch accessories")?;
+        }
+
+        let devices: Vec<Accessory> = response.json().context("Failed to parse accessories")?;
+
+        Ok(Self {
+            client,
+            base_url,
+            token,
+            devices,
+        })
+    }
 
-        // Authenticate
+    /// Logs in via `/api/auth/login` and persists the resulting token to the on-disk cache.
+    fn login(client: &Client, base_url: &str, username: &str, password: &str) -> Result<String> {
         println!("🔐 Authenticating...");
         let login_response: LoginResponse = client
             .post(format!("{}/api/auth/login", base_url))
@@ -109,23 +210,10 @@ impl SmartHomeAgent {
             .context("Failed to parse login response")?;
 
         let token = login_response.access_token;
-
-        // Discover devices
-        println!("🔍 Discovering devices...\n");
-        let devices: Vec<Accessory> = client
-            .get(format!("{}/api/accessories", base_url))
-            .bearer_auth(&token)
-            .send()
-            .context("Failed to fetch accessories")?
-            .json()
-            .context("Failed to parse accessories")?;
-
-        Ok(Self {
-            client,
-            base_url,
-            token,
-            devices,
-        })
+        if let Err(e) = save_cached_token(base_url, username, &token) {
+            eprintln!("⚠️  Failed to cache access token: {}", e);
+        }
+        Ok(token)
     }
 
     fn list_devices(&self) {
@@ -247,17 +335,194 @@ impl SmartHomeAgent {
     fn kitchen_lights(&self, state: &str) -> Result<()> {
         let lights = ["kuechentisch licht 1", "kuechentisch licht 2"];
         let is_on = matches!(state.to_lowercase().as_str(), "on" | "ein" | "1" | "true");
+        let value = if is_on { 1 } else { 0 };
+
+        let ops = lights
+            .iter()
+            .map(|light| (light.to_string(), "On".to_string(), serde_json::json!(value)))
+            .collect();
+
+        let results = control_many(self, ops);
+        if results.iter().any(|(_, r)| r.is_err()) {
+            anyhow::bail!("One or more kitchen lights failed");
+        }
+
+        Ok(())
+    }
 
-        for light in lights {
-            if is_on {
-                self.turn_on(light)?;
-            } else {
-                self.turn_off(light)?;
+    fn run_group(&self, config: &Config, group_name: &str, state: &str) -> Result<()> {
+        let devices = resolve_group_devices(config, group_name, &self.device_names())?;
+
+        let is_on = matches!(state.to_lowercase().as_str(), "on" | "ein" | "1" | "true");
+        let value = if is_on { 1 } else { 0 };
+
+        let ops = devices
+            .into_iter()
+            .map(|device| (device, "On".to_string(), serde_json::json!(value)))
+            .collect();
+
+        let results = control_many(self, ops);
+        if results.iter().any(|(_, r)| r.is_err()) {
+            anyhow::bail!("One or more devices in group '{}' failed", group_name);
+        }
+
+        Ok(())
+    }
+
+    fn run_scene(&self, config: &Config, scene_name: &str) -> Result<()> {
+        let scene = config
+            .scenes
+            .get(scene_name)
+            .with_context(|| format!("Unknown scene: {}", scene_name))?;
+
+        let known_devices = self.device_names();
+        for step in &scene.steps {
+            let devices = resolve_step_devices(config, step, &known_devices)?;
+            let mut ops = Vec::new();
+            for device in devices {
+                ops.extend(step_ops(device, step)?);
+            }
+            let results = control_many(self, ops);
+            if results.iter().any(|(_, r)| r.is_err()) {
+                anyhow::bail!("One or more devices in scene '{}' failed", scene_name);
             }
         }
 
         Ok(())
     }
+
+    fn set_hue(&self, device_name: &str, hue: u16) -> Result<()> {
+        self.control_device(device_name, "Hue", serde_json::json!(hue.min(360)))
+    }
+
+    fn set_saturation(&self, device_name: &str, saturation: u8) -> Result<()> {
+        self.control_device(
+            device_name,
+            "Saturation",
+            serde_json::json!(saturation.min(100)),
+        )
+    }
+
+    fn set_color_temp(&self, device_name: &str, mireds: u16) -> Result<()> {
+        self.control_device(
+            device_name,
+            "ColorTemperature",
+            serde_json::json!(mireds.clamp(140, 500)),
+        )
+    }
+
+    fn set_color(
+        &self,
+        device_name: &str,
+        hex: Option<&str>,
+        hue: Option<u16>,
+        saturation: Option<u8>,
+        temp: Option<u16>,
+    ) -> Result<()> {
+        if let Some(hex) = hex {
+            let (h, s, v) = hex_to_hsv(hex)?;
+            self.set_hue(device_name, h)?;
+            self.set_saturation(device_name, s)?;
+            self.set_brightness(device_name, v)?;
+            return Ok(());
+        }
+
+        if let Some(temp) = temp {
+            return self.set_color_temp(device_name, temp);
+        }
+
+        if hue.is_none() && saturation.is_none() {
+            anyhow::bail!("Color requires --hex, --temp, or --hue/--saturation");
+        }
+
+        if let Some(hue) = hue {
+            self.set_hue(device_name, hue)?;
+        }
+        if let Some(saturation) = saturation {
+            self.set_saturation(device_name, saturation)?;
+        }
+
+        Ok(())
+    }
+
+    fn watch_devices(&self, interval_secs: u64, filter: Option<&str>) -> Result<()> {
+        let watched: Vec<&Accessory> = self
+            .devices
+            .iter()
+            .filter(|d| matches!(d.device_type.as_str(), "Lightbulb" | "Switch" | "Outlet"))
+            .filter(|d| filter.is_none_or(|pattern| glob_match(pattern, &d.service_name)))
+            .collect();
+
+        if watched.is_empty() {
+            println!("No matching devices to watch");
+            return Ok(());
+        }
+
+        println!(
+            "👀 Watching {} device(s) every {}s (Ctrl+C to stop)\n",
+            watched.len(),
+            interval_secs
+        );
+
+        let mut last: HashMap<String, HashMap<String, serde_json::Value>> = watched
+            .iter()
+            .map(|d| (d.unique_id.clone(), d.values.clone().unwrap_or_default()))
+            .collect();
+
+        loop {
+            std::thread::sleep(Duration::from_secs(interval_secs));
+
+            let accessories: Vec<Accessory> = self
+                .client
+                .get(format!("{}/api/accessories", self.base_url))
+                .bearer_auth(&self.token)
+                .send()
+                .context("Failed to fetch accessories")?
+                .json()
+                .context("Failed to parse accessories")?;
+
+            let timestamp = Utc::now().to_rfc3339();
+
+            for device in &accessories {
+                let Some(previous) = last.get(&device.unique_id) else {
+                    continue;
+                };
+
+                let current = device.values.clone().unwrap_or_default();
+
+                for (key, value) in &current {
+                    if !matches!(
+                        key.as_str(),
+                        "On" | "Brightness" | "Hue" | "Saturation" | "ColorTemperature"
+                    ) {
+                        continue;
+                    }
+                    if previous.get(key) != Some(value) {
+                        println!(
+                            "[{}] {} {}: {:?} -> {:?}",
+                            timestamp,
+                            device.service_name,
+                            key,
+                            previous.get(key),
+                            value
+                        );
+                    }
+                }
+
+                last.insert(device.unique_id.clone(), current);
+            }
+        }
+    }
+}
+
+impl DeviceController for SmartHomeAgent {
+    fn control_device(&self, device: &str, characteristic: &str, value: serde_json::Value) -> Result<()> {
+        SmartHomeAgent::control_device(self, device, characteristic, value)
+    }
+
+    fn device_names(&self) -> Vec<String> {
+        self.devices.iter().map(|d| d.service_name.clone()).collect()
+    }
 }
 
 fn get_credentials_from_k8s() -> Result<(String, String)> {
@@ -317,7 +582,7 @@ fn main() -> Result<()> {
     };
 
     // Initialize agent
-    let agent = SmartHomeAgent::new(cli.url, username, password)?;
+    let agent = SmartHomeAgent::new(cli.url, username, password, cli.timeout)?;
 
     // Execute command
     match cli.command {
@@ -326,6 +591,22 @@ fn main() -> Result<()> {
         Commands::Off { device } => agent.turn_off(&device)?,
         Commands::Brightness { device, level } => agent.set_brightness(&device, level)?,
         Commands::Kitchen { state } => agent.kitchen_lights(&state)?,
+        Commands::Color {
+            device,
+            hex,
+            hue,
+            saturation,
+            temp,
+        } => agent.set_color(&device, hex.as_deref(), hue, saturation, temp)?,
+        Commands::Watch { interval, filter } => agent.watch_devices(interval, filter.as_deref())?,
+        Commands::Group { name, state } => {
+            let config = load_config(cli.config.as_deref())?;
+            agent.run_group(&config, &name, &state)?
+        }
+        Commands::Scene { name } => {
+            let config = load_config(cli.config.as_deref())?;
+            agent.run_scene(&config, &name)?
+        }
     }
 
     Ok(())
@@ -0,0 +1,233 @@
+use crate::color::hex_to_hsv;
+use crate::glob::glob_match;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// A reusable named list of device-name patterns.
+#[derive(Debug, Deserialize)]
+pub struct Group {
+    pub devices: Vec<String>,
+}
+
+/// A single step within a scene: either a group or a single device, plus an action to apply.
+#[derive(Debug, Deserialize)]
+pub struct SceneStep {
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub device: Option<String>,
+    pub action: String,
+    #[serde(default)]
+    pub brightness: Option<u8>,
+    #[serde(default)]
+    pub hex: Option<String>,
+    #[serde(default)]
+    pub hue: Option<u16>,
+    #[serde(default)]
+    pub saturation: Option<u8>,
+    #[serde(default)]
+    pub color_temp: Option<u16>,
+}
+
+/// An ordered list of steps run against groups or devices.
+#[derive(Debug, Deserialize)]
+pub struct Scene {
+    #[serde(default)]
+    pub steps: Vec<SceneStep>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub groups: HashMap<String, Group>,
+    #[serde(default)]
+    pub scenes: HashMap<String, Scene>,
+}
+
+pub fn default_config_path() -> Result<PathBuf> {
+    let home = env::var("HOME").context("HOME environment variable not set")?;
+    Ok(PathBuf::from(home).join(".config/smart-home-agent/scenes.toml"))
+}
+
+/// Loads the scenes/groups config, falling back to an empty config if none exists.
+pub fn load_config(explicit_path: Option<&str>) -> Result<Config> {
+    let path = match explicit_path {
+        Some(p) => PathBuf::from(p),
+        None => default_config_path()?,
+    };
+
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+
+    if path.extension().and_then(|e| e.to_str()) == Some("json") {
+        serde_json::from_str(&contents).context("Failed to parse JSON config")
+    } else {
+        toml::from_str(&contents).context("Failed to parse TOML config")
+    }
+}
+
+/// Expands a group's device-name patterns (which may use `*` wildcards, as accepted by
+/// `glob_match`) against `known_devices`, returning the concrete device names that matched.
+pub fn resolve_group_devices(
+    config: &Config,
+    group_name: &str,
+    known_devices: &[String],
+) -> Result<Vec<String>> {
+    let group = config
+        .groups
+        .get(group_name)
+        .with_context(|| format!("Unknown group: {}", group_name))?;
+
+    let mut devices = Vec::new();
+    for pattern in &group.devices {
+        let matches: Vec<&String> = known_devices.iter().filter(|name| glob_match(pattern, name)).collect();
+        if matches.is_empty() {
+            anyhow::bail!("Pattern '{}' in group '{}' matched no devices", pattern, group_name);
+        }
+        devices.extend(matches.into_iter().cloned());
+    }
+
+    Ok(devices)
+}
+
+/// Resolves a scene step to the concrete device names it applies to, expanding a group's
+/// patterns against `known_devices` or returning the single named device.
+pub fn resolve_step_devices(
+    config: &Config,
+    step: &SceneStep,
+    known_devices: &[String],
+) -> Result<Vec<String>> {
+    if let Some(group_name) = &step.group {
+        resolve_group_devices(config, group_name, known_devices)
+    } else if let Some(device) = &step.device {
+        Ok(vec![device.clone()])
+    } else {
+        anyhow::bail!("Scene step must specify a group or device")
+    }
+}
+
+/// Expands a scene step for one device into the `(device, characteristic, value)` ops it implies.
+pub fn step_ops(device: String, step: &SceneStep) -> Result<Vec<(String, String, serde_json::Value)>> {
+    if let Some(hex) = &step.hex {
+        let (h, s, v) = hex_to_hsv(hex)?;
+        return Ok(vec![
+            (device.clone(), "Hue".to_string(), serde_json::json!(h)),
+            (device.clone(), "Saturation".to_string(), serde_json::json!(s)),
+            (device, "Brightness".to_string(), serde_json::json!(v)),
+        ]);
+    }
+
+    let (characteristic, value) = match step.action.as_str() {
+        "on" => ("On", serde_json::json!(1)),
+        "off" => ("On", serde_json::json!(0)),
+        "brightness" => (
+            "Brightness",
+            serde_json::json!(step.brightness.context("brightness step requires brightness")?),
+        ),
+        "hue" => (
+            "Hue",
+            serde_json::json!(step.hue.context("hue step requires hue")?.min(360)),
+        ),
+        "saturation" => (
+            "Saturation",
+            serde_json::json!(
+                step.saturation
+                    .context("saturation step requires saturation")?
+                    .min(100)
+            ),
+        ),
+        "color_temp" => (
+            "ColorTemperature",
+            serde_json::json!(
+                step.color_temp
+                    .context("color_temp step requires color_temp")?
+                    .clamp(140, 500)
+            ),
+        ),
+        _ => anyhow::bail!("Unknown scene action: {}", step.action),
+    };
+
+    Ok(vec![(device, characteristic.to_string(), value)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_ops_hex_emits_hue_saturation_and_brightness() {
+        let step = SceneStep {
+            group: None,
+            device: None,
+            action: "on".to_string(),
+            brightness: None,
+            hex: Some("#330000".to_string()),
+            hue: None,
+            saturation: None,
+            color_temp: None,
+        };
+
+        let ops = step_ops("lamp".to_string(), &step).unwrap();
+        let characteristics: Vec<&str> = ops.iter().map(|(_, c, _)| c.as_str()).collect();
+        assert_eq!(characteristics, vec!["Hue", "Saturation", "Brightness"]);
+    }
+
+    #[test]
+    fn step_ops_rejects_unknown_action() {
+        let step = SceneStep {
+            group: None,
+            device: Some("lamp".to_string()),
+            action: "strobe".to_string(),
+            brightness: None,
+            hex: None,
+            hue: None,
+            saturation: None,
+            color_temp: None,
+        };
+
+        assert!(step_ops("lamp".to_string(), &step).is_err());
+    }
+
+    #[test]
+    fn resolve_group_devices_expands_wildcard_pattern() {
+        let mut config = Config::default();
+        config.groups.insert(
+            "kitchen".to_string(),
+            Group {
+                devices: vec!["kuechentisch *".to_string()],
+            },
+        );
+
+        let known_devices = vec![
+            "kuechentisch licht 1".to_string(),
+            "kuechentisch licht 2".to_string(),
+            "living room lamp".to_string(),
+        ];
+
+        let mut devices = resolve_group_devices(&config, "kitchen", &known_devices).unwrap();
+        devices.sort();
+        assert_eq!(devices, vec!["kuechentisch licht 1", "kuechentisch licht 2"]);
+    }
+
+    #[test]
+    fn resolve_group_devices_rejects_pattern_matching_nothing() {
+        let mut config = Config::default();
+        config.groups.insert(
+            "kitchen".to_string(),
+            Group {
+                devices: vec!["nonexistent *".to_string()],
+            },
+        );
+
+        let known_devices = vec!["kuechentisch licht 1".to_string()];
+        assert!(resolve_group_devices(&config, "kitchen", &known_devices).is_err());
+    }
+}
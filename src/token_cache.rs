@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedToken {
+    pub base_url: String,
+    pub username: String,
+    pub access_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// `~/.cache/smart-home-agent/token.json`, used to avoid re-authenticating on every run.
+pub fn token_cache_path() -> Result<PathBuf> {
+    let home = env::var("HOME").context("HOME environment variable not set")?;
+    Ok(PathBuf::from(home).join(".cache/smart-home-agent/token.json"))
+}
+
+/// Decodes the `exp` claim from a JWT's payload segment without verifying the signature.
+pub fn jwt_expiry(token: &str) -> Result<DateTime<Utc>> {
+    let payload = token
+        .split('.')
+        .nth(1)
+        .context("Malformed JWT: missing payload segment")?;
+    let decoded = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)
+        .context("Failed to base64-decode JWT payload")?;
+    let claims: serde_json::Value =
+        serde_json::from_slice(&decoded).context("Failed to parse JWT payload")?;
+    let exp = claims
+        .get("exp")
+        .and_then(|v| v.as_i64())
+        .context("JWT payload missing exp claim")?;
+
+    Utc.timestamp_opt(exp, 0)
+        .single()
+        .context("Invalid exp timestamp in JWT")
+}
+
+/// Returns a still-valid cached token for `base_url`/`username`, if one exists with a safety
+/// margin to spare. Keyed on both so switching accounts against the same Homebridge instance
+/// can't silently reuse another user's token.
+pub fn load_cached_token(base_url: &str, username: &str) -> Option<String> {
+    let path = token_cache_path().ok()?;
+    let contents = fs::read_to_string(path).ok()?;
+    let cached: CachedToken = serde_json::from_str(&contents).ok()?;
+
+    if cached.base_url != base_url || cached.username != username {
+        return None;
+    }
+
+    let margin = chrono::Duration::seconds(30);
+    if cached.expires_at - margin > Utc::now() {
+        Some(cached.access_token)
+    } else {
+        None
+    }
+}
+
+pub fn save_cached_token(base_url: &str, username: &str, access_token: &str) -> Result<()> {
+    let expires_at =
+        jwt_expiry(access_token).unwrap_or_else(|_| Utc::now() + chrono::Duration::hours(1));
+    let path = token_cache_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create token cache directory")?;
+        fs::set_permissions(parent, fs::Permissions::from_mode(0o700))
+            .context("Failed to lock down token cache directory permissions")?;
+    }
+
+    let cached = CachedToken {
+        base_url: base_url.to_string(),
+        username: username.to_string(),
+        access_token: access_token.to_string(),
+        expires_at,
+    };
+    fs::write(&path, serde_json::to_string(&cached)?).context("Failed to write token cache")?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))
+        .context("Failed to lock down token cache file permissions")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_jwt(payload_json: &str) -> String {
+        let payload = base64::encode_config(payload_json, base64::URL_SAFE_NO_PAD);
+        format!("header.{}.signature", payload)
+    }
+
+    #[test]
+    fn jwt_expiry_reads_exp_claim() {
+        let token = make_jwt(r#"{"exp": 1700000000}"#);
+        assert_eq!(jwt_expiry(&token).unwrap(), Utc.timestamp_opt(1700000000, 0).unwrap());
+    }
+
+    #[test]
+    fn jwt_expiry_rejects_missing_exp() {
+        let token = make_jwt(r#"{"sub": "alice"}"#);
+        assert!(jwt_expiry(&token).is_err());
+    }
+
+    #[test]
+    fn jwt_expiry_rejects_malformed_token() {
+        assert!(jwt_expiry("not-a-jwt").is_err());
+    }
+}